@@ -1,13 +1,19 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::str::from_utf8;
 
 use crate::util::{Annotation, Loc};
 
+/// Compact id of an interned identifier, returned by `Lexer::intern` and resolved back to its
+/// text with `Lexer::resolve`. Comparing two `Symbol`s is O(1) instead of comparing full strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
 /// Data type that represents Token.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub enum TokenKind {
-    Number(usize),
-    Identifier(String),
+pub enum TokenKind<'a> {
+    Number(&'a str),
+    Identifier(Symbol),
     Int,
     Plus,
     Minus,
@@ -16,13 +22,21 @@ pub enum TokenKind {
     LParen,
     RParen,
     Assignment,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
     Semicolon,
     Return,
 }
 
-pub type Token = Annotation<TokenKind>;
+pub type Token<'a> = Annotation<TokenKind<'a>>;
 
-impl Token {
+impl<'a> Token<'a> {
     pub fn plus(loc: Loc) -> Self {
         Self::new(TokenKind::Plus, loc)
     }
@@ -47,11 +61,11 @@ impl Token {
         Self::new(TokenKind::RParen, loc)
     }
 
-    pub fn identifier(ident: String, loc: Loc) -> Self {
+    pub fn identifier(ident: Symbol, loc: Loc) -> Self {
         Self::new(TokenKind::Identifier(ident), loc)
     }
 
-    pub fn number(n: usize, loc: Loc) -> Self {
+    pub fn number(n: &'a str, loc: Loc) -> Self {
         Self::new(TokenKind::Number(n), loc)
     }
 }
@@ -61,6 +75,9 @@ impl Token {
 pub enum LexErrorKind {
     InvalidChar(char),
     Eof,
+    /// The literal has no digits after its prefix, its digits don't fit its radix, or they
+    /// overflow `usize` once parsed.
+    InvalidNumber(String),
 }
 
 pub type LexError = Annotation<LexErrorKind>;
@@ -74,16 +91,22 @@ impl LexError {
     pub fn eof(loc: Loc) -> Self {
         LexError::new(LexErrorKind::Eof, loc)
     }
+
+    /// Malformed integer literal: a prefix with no digits after it, a bad digit for its radix,
+    /// or a value too big for `usize`.
+    pub fn invalid_number(text: String, loc: Loc) -> Self {
+        LexError::new(LexErrorKind::InvalidNumber(text), loc)
+    }
 }
 
-fn new_token(token_kind: TokenKind, start: usize, end: usize) -> Token {
+fn new_token(token_kind: TokenKind<'_>, start: usize, end: usize) -> Token<'_> {
     Token::new(token_kind, Loc(start, end))
 }
 
-fn reserve_keywords() -> HashMap<String, TokenKind> {
+fn reserve_keywords() -> HashMap<&'static str, TokenKind<'static>> {
     let mut keywords = HashMap::new();
-    keywords.insert("int".to_string(), TokenKind::Int);
-    keywords.insert("return".to_string(), TokenKind::Return);
+    keywords.insert("int", TokenKind::Int);
+    keywords.insert("return", TokenKind::Return);
     keywords
 }
 
@@ -94,7 +117,27 @@ pub struct Lexer<'a> {
     /// Position where an instance of `Lexer` is reading.
     pos: usize,
     /// `Vec` of processed tokens.
-    pub tokens: Vec<Token>,
+    pub tokens: Vec<Token<'a>>,
+    /// `Vec` of lexical errors collected by `lex_all`.
+    errors: Vec<LexError>,
+    /// Maps identifier text to the `Symbol` it was first interned as.
+    interner: HashMap<Rc<str>, Symbol>,
+    /// Reverse lookup from a `Symbol`'s index back to its original text, sharing the same
+    /// allocation as the `interner` key so each distinct identifier is only allocated once.
+    symbol_names: Vec<Rc<str>>,
+    /// Reserved words, built once and consulted by `lex_identifier`.
+    keywords: HashMap<&'static str, TokenKind<'static>>,
+    /// Byte offset each line starts at, in order; `line_starts[0]` is always `0`. Computed once
+    /// up front in `Lexer::new`, so `line_col`/`describe_error` are correct for any offset in
+    /// `input` regardless of how much of it the lexer has actually scanned past.
+    line_starts: Vec<usize>,
+}
+
+/// Byte offset each line of `input` starts at, in order; always starts with `0`.
+fn line_starts(input: &[u8]) -> Vec<usize> {
+    let mut line_starts = vec![0];
+    line_starts.extend(input.iter().enumerate().filter(|&(_, &b)| b == b'\n').map(|(i, _)| i + 1));
+    line_starts
 }
 
 impl<'a> Lexer<'a> {
@@ -104,131 +147,400 @@ impl<'a> Lexer<'a> {
             input: input.as_bytes(),
             pos: 0,
             tokens: Vec::new(),
+            errors: Vec::new(),
+            interner: HashMap::new(),
+            symbol_names: Vec::new(),
+            keywords: reserve_keywords(),
+            line_starts: line_starts(input.as_bytes()),
         }
     }
 
+    /// Intern `text`, returning the `Symbol` it was already assigned, or a freshly allocated
+    /// one if this is the first time `text` has been seen.
+    fn intern(&mut self, text: &str) -> Symbol {
+        if let Some(&sym) = self.interner.get(text) {
+            return sym;
+        }
+        let sym = Symbol(self.symbol_names.len() as u32);
+        let text: Rc<str> = Rc::from(text);
+        self.symbol_names.push(text.clone());
+        self.interner.insert(text, sym);
+        sym
+    }
+
+    /// Resolve a `Symbol` back to the identifier text it was interned from.
+    pub fn resolve(&self, sym: Symbol) -> &str {
+        &self.symbol_names[sym.0 as usize]
+    }
+
     /// Read all characters in a input code and push token into `tokens`.
-    pub fn lex(&mut self) -> Result<&Vec<Token>, LexError> {
-        let keywords = reserve_keywords();
-        while self.pos < self.input.len() {
-            match self.input[self.pos] {
-                b'+' => self.lex_plus(),
-                b'-' => self.lex_minus(),
-                b'*' => self.lex_asterisk(),
-                b'/' => self.lex_slash(),
-                b'(' => self.lex_lparen(),
-                b')' => self.lex_rparen(),
-                b'0'..=b'9' => self.lex_number(),
-                b'a'..=b'z' | b'A'..=b'Z' | b'_' => self.lex_identifier(&keywords),
-                b';' => self.lex_semicolon(),
-                b'=' => self.lex_assignment(),
-                b' ' | b'\n' | b'\t' => self.skip_spaces(),
-                b => {
-                    return Err(LexError::invalid_char(
-                        b as char,
-                        Loc(self.pos, self.pos + 1),
-                    ));
+    pub fn lex(&mut self) -> Result<&Vec<Token<'a>>, LexError> {
+        while let Some(result) = self.next_token() {
+            self.tokens.push(result?);
+        }
+
+        Ok(&self.tokens)
+    }
+
+    /// Read all characters in a input code like `lex`, but instead of aborting on the first
+    /// invalid character, record every `LexError` and keep going so all of them can be
+    /// reported in one pass. Returns `Ok` with the tokens only when no errors were collected.
+    pub fn lex_all(&mut self) -> Result<&Vec<Token<'a>>, &Vec<LexError>> {
+        loop {
+            match self.next_token() {
+                Some(Ok(token)) => self.tokens.push(token),
+                Some(Err(e)) => {
+                    // `next_token` may leave `self.pos` sitting right on the bad byte (it only
+                    // ever advances past whitespace/comments before discovering it), so recover
+                    // by the error's own span rather than comparing against the position from
+                    // before this call, or the same error gets reported twice.
+                    self.pos = self.pos.max(e.loc.0 + 1);
+                    self.errors.push(e);
                 }
+                None => break,
             }
         }
 
-        Ok(&self.tokens)
+        if self.errors.is_empty() {
+            Ok(&self.tokens)
+        } else {
+            Err(&self.errors)
+        }
+    }
+
+    /// Produce the next single token, skipping any whitespace and comments first. Returns
+    /// `None` once the input is exhausted. Consumers that want the full token stream up front
+    /// should use `lex`/`lex_all`; consumers that want to act on tokens as they arrive, or stop
+    /// early, can call this directly or iterate over the `Lexer` itself.
+    pub fn next_token(&mut self) -> Option<Result<Token<'a>, LexError>> {
+        loop {
+            match self.peek()? {
+                b' ' | b'\n' | b'\t' => self.skip_spaces(),
+                b'/' => match self.skip_comment_if_present() {
+                    Ok(true) => continue,
+                    Ok(false) => break,
+                    Err(e) => return Some(Err(e)),
+                },
+                _ => break,
+            }
+        }
+
+        let result = match self.peek()? {
+            b'+' => Ok(self.lex_plus()),
+            b'-' => Ok(self.lex_minus()),
+            b'*' => Ok(self.lex_asterisk()),
+            b'/' => Ok(self.lex_slash()),
+            b'(' => Ok(self.lex_lparen()),
+            b')' => Ok(self.lex_rparen()),
+            b'0'..=b'9' => self.lex_number(),
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => Ok(self.lex_identifier()),
+            b';' => Ok(self.lex_semicolon()),
+            b'=' => Ok(self.lex_assignment()),
+            b'!' => self.lex_bang(),
+            b'<' => Ok(self.lex_less()),
+            b'>' => Ok(self.lex_greater()),
+            b'&' => self.lex_and(),
+            b'|' => self.lex_or(),
+            b => Err(LexError::invalid_char(b as char, Loc(self.pos, self.pos + 1))),
+        };
+        Some(result)
     }
 
-    fn lex_plus(&mut self) {
-        self.tokens.push(token!(Plus, self.pos, self.pos + 1));
+    fn lex_plus(&mut self) -> Token<'a> {
+        let token = token!(Plus, self.pos, self.pos + 1);
         self.pos += 1;
+        token
     }
 
-    fn lex_minus(&mut self) {
-        self.tokens.push(token!(Minus, self.pos, self.pos + 1));
+    fn lex_minus(&mut self) -> Token<'a> {
+        let token = token!(Minus, self.pos, self.pos + 1);
         self.pos += 1;
+        token
     }
 
-    fn lex_asterisk(&mut self) {
-        self.tokens.push(token!(Asterisk, self.pos, self.pos + 1));
+    fn lex_asterisk(&mut self) -> Token<'a> {
+        let token = token!(Asterisk, self.pos, self.pos + 1);
         self.pos += 1;
+        token
     }
 
-    fn lex_slash(&mut self) {
-        self.tokens.push(token!(Slash, self.pos, self.pos + 1));
+    /// Lex a bare `/`. `skip_comment_if_present` has already ruled out `//` and `/*` by the
+    /// time this is called.
+    fn lex_slash(&mut self) -> Token<'a> {
+        let token = token!(Slash, self.pos, self.pos + 1);
         self.pos += 1;
+        token
+    }
+
+    /// If a line or block comment starts at `self.pos`, consume it (updating `self.pos` so
+    /// later `Loc`s stay accurate) and return `Ok(true)`. Otherwise leave `self.pos` untouched
+    /// and return `Ok(false)`.
+    fn skip_comment_if_present(&mut self) -> Result<bool, LexError> {
+        if self.peek() != Some(b'/') {
+            return Ok(false);
+        }
+        match self.peek_next() {
+            Some(b'/') => {
+                self.pos = self.recognize_multiple_char(|b| b != b'\n');
+                Ok(true)
+            }
+            Some(b'*') => {
+                self.skip_block_comment()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Skip a `/* ... */` block comment, supporting nesting, starting at `self.pos`.
+    /// Returns a `LexError::eof` pointing at the opening `/*` if the comment is never closed.
+    fn skip_block_comment(&mut self) -> Result<(), LexError> {
+        let start = self.pos;
+        self.pos += 2;
+        let mut depth = 1;
+        while depth > 0 {
+            match (self.input.get(self.pos), self.input.get(self.pos + 1)) {
+                (Some(b'/'), Some(b'*')) => {
+                    depth += 1;
+                    self.pos += 2;
+                }
+                (Some(b'*'), Some(b'/')) => {
+                    depth -= 1;
+                    self.pos += 2;
+                }
+                (Some(_), _) => {
+                    self.pos += 1;
+                }
+                (None, _) => return Err(LexError::eof(Loc(start, start + 2))),
+            }
+        }
+        Ok(())
     }
 
-    fn lex_lparen(&mut self) {
-        self.tokens.push(token!(LParen, self.pos, self.pos + 1));
+    fn lex_lparen(&mut self) -> Token<'a> {
+        let token = token!(LParen, self.pos, self.pos + 1);
         self.pos += 1;
+        token
     }
 
-    fn lex_rparen(&mut self) {
-        self.tokens.push(token!(RParen, self.pos, self.pos + 1));
+    fn lex_rparen(&mut self) -> Token<'a> {
+        let token = token!(RParen, self.pos, self.pos + 1);
         self.pos += 1;
+        token
     }
 
-    fn lex_number(&mut self) {
+    /// Lex an integer literal: decimal, or `0x`/`0b`/`0o`-prefixed hex/binary/octal, with `_`
+    /// allowed anywhere among the digits as a visual separator (e.g. `1_000_000`, `0xFF_FF`).
+    fn lex_number(&mut self) -> Result<Token<'a>, LexError> {
         let start = self.pos;
-        let end = self.recognize_multiple_char(|b| b"0123456789".contains(&b));
-        let num = from_utf8(&self.input[start..end]).unwrap().parse().unwrap();
+        let input = self.input;
+
+        let (radix, digits_start): (u32, usize) = match (input.get(start), input.get(start + 1)) {
+            (Some(b'0'), Some(b'x' | b'X')) => (16, start + 2),
+            (Some(b'0'), Some(b'b' | b'B')) => (2, start + 2),
+            (Some(b'0'), Some(b'o' | b'O')) => (8, start + 2),
+            _ => (10, start),
+        };
+        // For a prefixed literal, swallow the whole alphanumeric run so a bad digit (like the
+        // `Z`s in `0xZZ`) ends up inside the literal's span instead of trailing off as its own
+        // token; a bare decimal literal only ever contains `0`-`9` so digits suffice there.
+        let is_digit = |b: u8| {
+            b == b'_' || if radix == 10 { b.is_ascii_digit() } else { b.is_ascii_alphanumeric() }
+        };
+        let end = self.recognize_from(digits_start, is_digit);
+
+        let text = from_utf8(&input[start..end]).unwrap();
+        let digits: String = from_utf8(&input[digits_start..end])
+            .unwrap()
+            .chars()
+            .filter(|&c| c != '_')
+            .collect();
+        if digits.is_empty() || usize::from_str_radix(&digits, radix).is_err() {
+            self.pos = end;
+            return Err(LexError::invalid_number(text.to_string(), Loc(start, end)));
+        }
 
-        self.tokens.push(token!(Number(num), start, end));
         self.pos = end;
+        Ok(token!(Number(text), start, end))
     }
 
-    fn lex_identifier(&mut self, keywords: &HashMap<String, TokenKind>) {
+    fn lex_identifier(&mut self) -> Token<'a> {
         let start = self.pos;
         let end = self.recognize_multiple_char(|b| b.is_ascii_alphanumeric() || b == b'_');
-        let identifier = from_utf8(&self.input[start..end]).unwrap();
-        let identifier = identifier.to_string();
-        match keywords.get(&identifier) {
-            Some(token_kind) => self.tokens.push(new_token(token_kind.clone(), start, end)),
-            None => self.tokens.push(token!(Identifier(identifier), start, end)),
-        }
+        let input = self.input;
+        let identifier = from_utf8(&input[start..end]).unwrap();
+        let keyword = self.keywords.get(identifier).cloned();
+        let token = match keyword {
+            Some(token_kind) => new_token(token_kind, start, end),
+            None => {
+                let sym = self.intern(identifier);
+                token!(Identifier(sym), start, end)
+            }
+        };
         self.pos = end;
+        token
     }
 
-    /// Read a code while `f` returns `true` and return position of the end of fragment; each character in the fragment satisfies `f`.
-    fn recognize_multiple_char(&mut self, mut f: impl FnMut(u8) -> bool) -> usize {
-        let mut pos = self.pos;
+    /// Like `recognize_multiple_char`, but starts scanning from an arbitrary position instead
+    /// of `self.pos`, for callers (like `lex_number`) that need to skip a prefix first.
+    fn recognize_from(&self, start: usize, mut f: impl FnMut(u8) -> bool) -> usize {
+        let mut pos = start;
         while pos < self.input.len() && f(self.input[pos]) {
             pos += 1;
         }
         pos
     }
 
-    fn lex_semicolon(&mut self) {
-        self.tokens.push(token!(Semicolon, self.pos, self.pos + 1));
-        self.pos += 1;
+    /// Read a code while `f` returns `true` and return position of the end of fragment; each character in the fragment satisfies `f`.
+    fn recognize_multiple_char(&self, f: impl FnMut(u8) -> bool) -> usize {
+        self.recognize_from(self.pos, f)
     }
 
-    fn lex_assignment(&mut self) {
-        self.tokens.push(token!(Assignment, self.pos, self.pos + 1));
+    /// Byte at the current reading position, if any.
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    /// Byte one past the current reading position, if any.
+    fn peek_next(&self) -> Option<u8> {
+        self.input.get(self.pos + 1).copied()
+    }
+
+    fn lex_semicolon(&mut self) -> Token<'a> {
+        let token = token!(Semicolon, self.pos, self.pos + 1);
         self.pos += 1;
+        token
+    }
+
+    /// Lex `=` or, with one byte of lookahead, `==`.
+    fn lex_assignment(&mut self) -> Token<'a> {
+        if self.peek_next() == Some(b'=') {
+            let token = token!(Equal, self.pos, self.pos + 2);
+            self.pos += 2;
+            token
+        } else {
+            let token = token!(Assignment, self.pos, self.pos + 1);
+            self.pos += 1;
+            token
+        }
+    }
+
+    /// Lex `!=`; a lone `!` has no meaning yet, so it is an invalid char.
+    fn lex_bang(&mut self) -> Result<Token<'a>, LexError> {
+        if self.peek_next() == Some(b'=') {
+            let token = token!(NotEqual, self.pos, self.pos + 2);
+            self.pos += 2;
+            Ok(token)
+        } else {
+            Err(LexError::invalid_char('!', Loc(self.pos, self.pos + 1)))
+        }
+    }
+
+    /// Lex `<` or, with one byte of lookahead, `<=`.
+    fn lex_less(&mut self) -> Token<'a> {
+        if self.peek_next() == Some(b'=') {
+            let token = token!(LessEqual, self.pos, self.pos + 2);
+            self.pos += 2;
+            token
+        } else {
+            let token = token!(Less, self.pos, self.pos + 1);
+            self.pos += 1;
+            token
+        }
+    }
+
+    /// Lex `>` or, with one byte of lookahead, `>=`.
+    fn lex_greater(&mut self) -> Token<'a> {
+        if self.peek_next() == Some(b'=') {
+            let token = token!(GreaterEqual, self.pos, self.pos + 2);
+            self.pos += 2;
+            token
+        } else {
+            let token = token!(Greater, self.pos, self.pos + 1);
+            self.pos += 1;
+            token
+        }
+    }
+
+    /// Lex `&&`; a lone `&` has no meaning yet, so it is an invalid char.
+    fn lex_and(&mut self) -> Result<Token<'a>, LexError> {
+        if self.peek_next() == Some(b'&') {
+            let token = token!(And, self.pos, self.pos + 2);
+            self.pos += 2;
+            Ok(token)
+        } else {
+            Err(LexError::invalid_char('&', Loc(self.pos, self.pos + 1)))
+        }
+    }
+
+    /// Lex `||`; a lone `|` has no meaning yet, so it is an invalid char.
+    fn lex_or(&mut self) -> Result<Token<'a>, LexError> {
+        if self.peek_next() == Some(b'|') {
+            let token = token!(Or, self.pos, self.pos + 2);
+            self.pos += 2;
+            Ok(token)
+        } else {
+            Err(LexError::invalid_char('|', Loc(self.pos, self.pos + 1)))
+        }
     }
 
     fn skip_spaces(&mut self) {
-        let pos = self.recognize_multiple_char(|b| b" \n\t".contains(&b));
-        self.pos = pos;
+        self.pos = self.recognize_multiple_char(|b| b" \n\t".contains(&b));
+    }
+
+    /// Convert a byte offset into the 1-indexed `(line, column)` it falls on, for rendering a
+    /// `LexError`'s `Loc` as `line L, column C` the way a compiler diagnostic would.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        (line, offset - line_start + 1)
+    }
+
+    /// Render `err` as a human-readable diagnostic: its position, the source line it occurred
+    /// on, and a `^` underline spanning its `Loc`.
+    pub fn describe_error(&self, err: &LexError) -> String {
+        let Loc(start, end) = err.loc;
+        let (line, column) = self.line_col(start);
+        let line_start = self.line_starts[line - 1];
+        let line_end = self.input[line_start..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(self.input.len(), |i| line_start + i);
+        let source_line = from_utf8(&self.input[line_start..line_end]).unwrap_or("");
+        let underline = " ".repeat(column - 1) + &"^".repeat(end.saturating_sub(start).max(1));
+        format!("line {line}, column {column}\n{source_line}\n{underline}")
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::lexer::{Lexer, Token, TokenKind};
+    use crate::lexer::{Lexer, Symbol, Token, TokenKind};
     use crate::util::Loc;
 
     #[test]
     fn test_lexer() {
-        let mut lexer = Lexer::new("+/*(-)");
+        // `/` directly followed by `*` always opens a block comment (see
+        // `skip_comment_if_present`), so this exercises the single-char operators back-to-back
+        // in an order that never puts `*` directly after `/`.
+        let mut lexer = Lexer::new("+-()*/");
         let tokens = lexer.lex();
         assert_eq!(
             tokens,
             Ok(&vec![
                 token!(Plus, 0, 1),
-                token!(Slash, 1, 2),
-                token!(Asterisk, 2, 3),
-                token!(LParen, 3, 4),
-                token!(Minus, 4, 5),
-                token!(RParen, 5, 6),
+                token!(Minus, 1, 2),
+                token!(LParen, 2, 3),
+                token!(RParen, 3, 4),
+                token!(Asterisk, 4, 5),
+                token!(Slash, 5, 6),
             ]),
         );
 
@@ -237,22 +549,22 @@ mod tests {
         assert_eq!(
             tokens,
             Ok(&vec![
-                token!(Identifier("a".to_string()), 0, 1),
+                token!(Identifier(Symbol(0)), 0, 1),
                 token!(Assignment, 2, 3),
-                token!(Number(3), 4, 5),
+                token!(Number("3"), 4, 5),
                 token!(Semicolon, 5, 6),
-                token!(Identifier("b".to_string()), 7, 8),
+                token!(Identifier(Symbol(1)), 7, 8),
                 token!(Assignment, 9, 10),
-                token!(Number(2), 11, 12),
+                token!(Number("2"), 11, 12),
                 token!(Semicolon, 12, 13),
-                token!(Identifier("c".to_string()), 14, 15),
+                token!(Identifier(Symbol(2)), 14, 15),
                 token!(Assignment, 16, 17),
-                token!(Identifier("a".to_string()), 18, 19),
+                token!(Identifier(Symbol(0)), 18, 19),
                 token!(Asterisk, 20, 21),
-                token!(Identifier("b".to_string()), 22, 23),
+                token!(Identifier(Symbol(1)), 22, 23),
                 token!(Semicolon, 23, 24),
                 token!(Return, 25, 31),
-                token!(Identifier("c".to_string()), 32, 33),
+                token!(Identifier(Symbol(2)), 32, 33),
                 token!(Semicolon, 33, 34),
             ]),
         );
@@ -265,4 +577,206 @@ mod tests {
         let tokens = lexer.lex();
         assert_eq!(tokens, Err(LexError::invalid_char('$', Loc(2, 3))),);
     }
+
+    #[test]
+    fn test_lexer_lex_all_collects_every_error() {
+        use crate::lexer::LexError;
+        let mut lexer = Lexer::new("1 $ 2 @ 3");
+        let tokens = lexer.lex_all();
+        assert_eq!(
+            tokens,
+            Err(&vec![
+                LexError::invalid_char('$', Loc(2, 3)),
+                LexError::invalid_char('@', Loc(6, 7)),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_lexer_lex_all_ok_when_no_errors() {
+        let mut lexer = Lexer::new("+-");
+        let tokens = lexer.lex_all();
+        assert_eq!(tokens, Ok(&vec![token!(Plus, 0, 1), token!(Minus, 1, 2)]),);
+    }
+
+    #[test]
+    fn test_lexer_skips_line_comment() {
+        let mut lexer = Lexer::new("1 // this is a comment\n+ 2");
+        let tokens = lexer.lex();
+        assert_eq!(
+            tokens,
+            Ok(&vec![
+                token!(Number("1"), 0, 1),
+                token!(Plus, 23, 24),
+                token!(Number("2"), 25, 26),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_lexer_skips_nested_block_comment() {
+        let mut lexer = Lexer::new("1 /* outer /* inner */ still open */ + 2");
+        let tokens = lexer.lex();
+        assert_eq!(
+            tokens,
+            Ok(&vec![token!(Number("1"), 0, 1), token!(Plus, 37, 38), token!(Number("2"), 39, 40)]),
+        );
+    }
+
+    #[test]
+    fn test_lexer_unterminated_block_comment_is_eof_error() {
+        use crate::lexer::LexError;
+        let mut lexer = Lexer::new("1 /* never closed");
+        let tokens = lexer.lex();
+        assert_eq!(tokens, Err(LexError::eof(Loc(2, 4))));
+    }
+
+    #[test]
+    fn test_lexer_comparison_and_logical_operators() {
+        let mut lexer = Lexer::new("a == b != c < d <= e > f >= g && h || i = j");
+        let tokens = lexer.lex();
+        assert_eq!(
+            tokens,
+            Ok(&vec![
+                token!(Identifier(Symbol(0)), 0, 1),
+                token!(Equal, 2, 4),
+                token!(Identifier(Symbol(1)), 5, 6),
+                token!(NotEqual, 7, 9),
+                token!(Identifier(Symbol(2)), 10, 11),
+                token!(Less, 12, 13),
+                token!(Identifier(Symbol(3)), 14, 15),
+                token!(LessEqual, 16, 18),
+                token!(Identifier(Symbol(4)), 19, 20),
+                token!(Greater, 21, 22),
+                token!(Identifier(Symbol(5)), 23, 24),
+                token!(GreaterEqual, 25, 27),
+                token!(Identifier(Symbol(6)), 28, 29),
+                token!(And, 30, 32),
+                token!(Identifier(Symbol(7)), 33, 34),
+                token!(Or, 35, 37),
+                token!(Identifier(Symbol(8)), 38, 39),
+                token!(Assignment, 40, 41),
+                token!(Identifier(Symbol(9)), 42, 43),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_lexer_lone_bang_ampersand_pipe_are_invalid() {
+        use crate::lexer::LexError;
+        let mut lexer = Lexer::new("!a");
+        let tokens = lexer.lex();
+        assert_eq!(tokens, Err(LexError::invalid_char('!', Loc(0, 1))));
+
+        let mut lexer = Lexer::new("&a");
+        let tokens = lexer.lex();
+        assert_eq!(tokens, Err(LexError::invalid_char('&', Loc(0, 1))));
+
+        let mut lexer = Lexer::new("|a");
+        let tokens = lexer.lex();
+        assert_eq!(tokens, Err(LexError::invalid_char('|', Loc(0, 1))));
+    }
+
+    #[test]
+    fn test_lexer_interns_repeated_identifiers_to_the_same_symbol() {
+        let mut lexer = Lexer::new("foo foo bar");
+        let tokens = lexer.lex();
+        assert_eq!(
+            tokens,
+            Ok(&vec![
+                token!(Identifier(Symbol(0)), 0, 3),
+                token!(Identifier(Symbol(0)), 4, 7),
+                token!(Identifier(Symbol(1)), 8, 11),
+            ]),
+        );
+        assert_eq!(lexer.resolve(Symbol(0)), "foo");
+        assert_eq!(lexer.resolve(Symbol(1)), "bar");
+    }
+
+    #[test]
+    fn test_lexer_hex_binary_octal_and_underscore_separated_literals() {
+        let mut lexer = Lexer::new("0xFF_FF 0b10_10 0o17 1_000_000");
+        let tokens = lexer.lex();
+        assert_eq!(
+            tokens,
+            Ok(&vec![
+                token!(Number("0xFF_FF"), 0, 7),
+                token!(Number("0b10_10"), 8, 15),
+                token!(Number("0o17"), 16, 20),
+                token!(Number("1_000_000"), 21, 30),
+            ]),
+        );
+    }
+
+    #[test]
+    fn test_lexer_invalid_number_reports_error_instead_of_panicking() {
+        use crate::lexer::LexError;
+        let mut lexer = Lexer::new("0xZZ");
+        let tokens = lexer.lex();
+        assert_eq!(
+            tokens,
+            Err(LexError::invalid_number("0xZZ".to_string(), Loc(0, 4))),
+        );
+
+        let mut lexer = Lexer::new("99999999999999999999999999999999999999");
+        let tokens = lexer.lex();
+        assert_eq!(
+            tokens,
+            Err(LexError::invalid_number(
+                "99999999999999999999999999999999999999".to_string(),
+                Loc(0, 38),
+            )),
+        );
+    }
+
+    #[test]
+    fn test_lexer_next_token_yields_one_token_per_call() {
+        let mut lexer = Lexer::new("1 + 2");
+        assert_eq!(lexer.next_token(), Some(Ok(token!(Number("1"), 0, 1))));
+        assert_eq!(lexer.next_token(), Some(Ok(token!(Plus, 2, 3))));
+        assert_eq!(lexer.next_token(), Some(Ok(token!(Number("2"), 4, 5))));
+        assert_eq!(lexer.next_token(), None);
+    }
+
+    #[test]
+    fn test_lexer_line_col_tracks_newlines_in_code_and_block_comments() {
+        let mut lexer = Lexer::new("a\nbb\n/* x\ny */\nccc");
+        lexer.lex().unwrap();
+        assert_eq!(lexer.line_col(0), (1, 1));
+        assert_eq!(lexer.line_col(2), (2, 1));
+        assert_eq!(lexer.line_col(5), (3, 1));
+        assert_eq!(lexer.line_col(15), (5, 1));
+    }
+
+    #[test]
+    fn test_lexer_line_col_is_correct_before_that_offset_has_been_lexed() {
+        let mut lexer = Lexer::new("a\nb");
+        lexer.next().unwrap().unwrap();
+        assert_eq!(lexer.line_col(2), (2, 1));
+    }
+
+    #[test]
+    fn test_lexer_describe_error_renders_line_and_caret_underline() {
+        use crate::lexer::LexError;
+        let mut lexer = Lexer::new("1 + 2\n3 $ 4");
+        let tokens = lexer.lex();
+        let err = tokens.unwrap_err();
+        assert_eq!(err, LexError::invalid_char('$', Loc(8, 9)));
+        assert_eq!(lexer.line_col(8), (2, 3));
+        assert_eq!(lexer.describe_error(&err), "line 2, column 3\n3 $ 4\n  ^");
+    }
+
+    #[test]
+    fn test_lexer_is_an_iterator() {
+        let lexer = Lexer::new("1 + 2");
+        let tokens: Result<Vec<_>, _> = lexer.collect();
+        assert_eq!(
+            tokens,
+            Ok(vec![
+                token!(Number("1"), 0, 1),
+                token!(Plus, 2, 3),
+                token!(Number("2"), 4, 5),
+            ]),
+        );
+    }
 }